@@ -1,12 +1,20 @@
-//! A simplified implementation of the classic game "Breakout".
+//! A simplified implementation of the classic game "Breakout", now playable
+//! as a deterministic, rollback-netcoded two-player match over `bevy_ggrs`,
+//! with collision and response handled by `bevy_rapier2d` instead of a
+//! hand-rolled AABB/circle test. Levels can be larger than the viewport; a
+//! `camera_follow` system tracks the ball and clamps to the level's bounds.
 //!
 //! Demonstrates Bevy's stepping capabilities if compiled with the `bevy_debug_stepping` feature.
 
-use bevy::{
-    math::bounding::{Aabb2d, BoundingCircle, BoundingVolume, IntersectsVolume},
-    prelude::*,
-    sprite::MaterialMesh2dBundle,
+use bevy::{color::Mix, prelude::*, sprite::MaterialMesh2dBundle};
+use bevy_ggrs::{
+    ggrs::{self, PlayerType, SessionBuilder},
+    AddRollbackCommandExtension, GgrsApp, GgrsPlugin, GgrsSchedule, LocalInputs, LocalPlayers,
+    PlayerInputs, Session,
 };
+use bevy_rapier2d::prelude::*;
+use bytemuck::{Pod, Zeroable};
+use std::net::SocketAddr;
 
 mod stepping;
 
@@ -25,12 +33,6 @@ const BALL_SPEED: f32 = 400.0;
 const INITIAL_BALL_DIRECTION: Vec2 = Vec2::new(0.5, -0.5);
 
 const WALL_THICKNESS: f32 = 10.0;
-// x coordinates
-const LEFT_WALL: f32 = -450.;
-const RIGHT_WALL: f32 = 450.;
-// y coordinates
-const BOTTOM_WALL: f32 = -300.;
-const TOP_WALL: f32 = 300.;
 
 const BRICK_SIZE: Vec2 = Vec2::new(100., 30.);
 // These values are exact
@@ -46,131 +48,449 @@ const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
 const BACKGROUND_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
 const PADDLE_COLOR: Color = Color::srgb(0.3, 0.3, 0.7);
 const BALL_COLOR: Color = Color::srgb(1.0, 0.5, 0.5);
-const BRICK_COLOR: Color = Color::srgb(0.5, 0.5, 1.0);
 const WALL_COLOR: Color = Color::srgb(0.8, 0.8, 0.8);
 const TEXT_COLOR: Color = Color::srgb(0.5, 0.5, 1.0);
 const SCORE_COLOR: Color = Color::srgb(1.0, 0.5, 0.5);
 
-struct Level {
-    brick_layout: Vec<Vec<Option<Brick>>>,
+// Rollback netcode needs a fixed, deterministic step: every peer must advance
+// gameplay by the exact same amount of simulated time each frame, so we no
+// longer scale movement by `Time::delta_seconds()`.
+const FPS: usize = 60;
+const DELTA_TIME: f32 = 1.0 / FPS as f32;
+const MAX_PREDICTION_WINDOW: usize = 10;
+const INPUT_DELAY: usize = 2;
+
+const INPUT_LEFT: u8 = 1 << 0;
+const INPUT_RIGHT: u8 = 1 << 1;
+
+/// The `ggrs::Config` impl that ties together our input type, the session's
+/// saved-state type and the transport address type used for P2P sessions.
+#[derive(Debug)]
+struct GgrsConfig;
+
+impl ggrs::Config for GgrsConfig {
+    type Input = BoxInput;
+    type State = u8;
+    type Address = SocketAddr;
 }
 
-// Define the levels using fixed-size arrays
+/// A single frame of paddle input, packed into a bitmask so it round-trips
+/// through GGRS's `Pod`/`Zeroable` input serialization.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+struct BoxInput {
+    inp: u8,
+}
+
+// Levels are no longer baked into the binary as nested `Vec`s; they're parsed
+// at runtime from `assets/levels/*.level` by `LevelAssetLoader` below, so
+// designers can add or edit a level without recompiling.
+mod level_asset;
+use level_asset::{GridCell, LevelAsset, LevelAssetLoader, DEFAULT_ARENA_SIZE};
+
+/// The arena's (left, right, bottom, top) bounds, derived from the current
+/// level's `arena_size` rather than the fixed screen constants, so levels can
+/// be larger than the viewport. Centered on the origin, same as the old
+/// fixed walls.
+#[derive(Resource, Clone, Copy)]
+struct LevelBounds {
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+}
+
+impl LevelBounds {
+    fn from_arena_size(size: Vec2) -> Self {
+        LevelBounds {
+            left: -size.x / 2.0,
+            right: size.x / 2.0,
+            bottom: -size.y / 2.0,
+            top: size.y / 2.0,
+        }
+    }
+
+    fn width(&self) -> f32 {
+        self.right - self.left
+    }
 
-fn create_level_1() -> Level {
-    Level {
-        brick_layout: vec![
-            vec![Some(Brick), None, Some(Brick)],
-            vec![Some(Brick), Some(Brick), Some(Brick)],
-            vec![Some(Brick), Some(Brick), Some(Brick)],
-            vec![Some(Brick), Some(Brick), Some(Brick)],
-        ],
+    fn height(&self) -> f32 {
+        self.top - self.bottom
     }
 }
 
-fn create_level_2() -> Level {
-    Level {
-        brick_layout: vec![
-            vec![Some(Brick), Some(Brick), Some(Brick)],
-            vec![Some(Brick), None, Some(Brick)],
-            vec![Some(Brick), Some(Brick), Some(Brick)],
-            vec![Some(Brick), Some(Brick), Some(Brick)],
-        ],
+impl Default for LevelBounds {
+    fn default() -> Self {
+        Self::from_arena_size(DEFAULT_ARENA_SIZE)
     }
 }
 
+/// The area the camera frames around the ball; matches the original fixed
+/// arena size so existing levels look unchanged until they opt into a bigger
+/// `arena_size`.
+const VIEWPORT_SIZE: Vec2 = Vec2::new(900.0, 600.0);
+// Higher = camera catches up to the ball faster; this is purely cosmetic
+// smoothing, not gameplay state, so it's fine to scale by wall-clock time.
+const CAMERA_FOLLOW_SPEED: f32 = 4.0;
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 struct GameState {
-    levels: Vec<Level>,
+    // One handle per file under `assets/levels/`, in sorted-filename order.
+    levels: Vec<Handle<LevelAsset>>,
     current_level: usize,
+    // Whether `current_level`'s bricks have already been spawned. Flipped back
+    // to `false` by `next_level` so `spawn_pending_level` picks up the change.
+    level_spawned: bool,
 }
 
 impl Default for GameState {
     fn default() -> Self {
         GameState {
-            levels: vec![create_level_1(), create_level_2()],
+            levels: Vec::new(),
             current_level: 0,
+            level_spawned: false,
         }
     }
 }
 
-
 fn next_level(game_state: &mut GameState) {
     if game_state.current_level + 1 < game_state.levels.len() {
         game_state.current_level += 1;
+        game_state.level_spawned = false;
     } else {
         println!("You have completed all levels!");
     }
 }
 
+/// Scans `assets/levels/` for `.level` files, sorted by filename, and kicks
+/// off an asset load for each. The returned handles drive `current_level`
+/// progression; bricks are spawned once their handle resolves.
+fn discover_levels(asset_server: &AssetServer) -> Vec<Handle<LevelAsset>> {
+    let levels_dir = std::path::Path::new("assets/levels");
+    let mut file_names: Vec<_> = std::fs::read_dir(levels_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name())
+                .filter(|name| name.to_string_lossy().ends_with(".level"))
+                .collect()
+        })
+        .unwrap_or_default();
+    file_names.sort();
+
+    file_names
+        .into_iter()
+        .map(|file_name| asset_server.load(format!("levels/{}", file_name.to_string_lossy())))
+        .collect()
+}
 
+/// Which networking mode to launch the match in, selected from the command line:
+///
+/// * `sync-test [num-players]` runs a local SyncTest session with `num-players`
+///   local players (default 2), which rolls back and re-simulates frames and
+///   panics if the resulting rollback checksums ever diverge. Useful for
+///   catching non-determinism locally, without any real network traffic.
+/// * `spectate <host-addr>` connects as a read-only observer of a running match.
+/// * `p2p <local-port> <remote-addr>` starts a real two-player P2P session.
+enum NetArgs {
+    SyncTest { num_players: usize },
+    Spectate { host_addr: SocketAddr },
+    P2P {
+        local_port: u16,
+        remote_addr: SocketAddr,
+    },
+}
+
+fn parse_net_args() -> NetArgs {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("spectate") => NetArgs::Spectate {
+            host_addr: args[2].parse().expect("invalid host address"),
+        },
+        Some("p2p") => NetArgs::P2P {
+            local_port: args[2].parse().expect("invalid local port"),
+            remote_addr: args[3].parse().expect("invalid remote address"),
+        },
+        Some("sync-test") => NetArgs::SyncTest {
+            num_players: args
+                .get(2)
+                .map(|n| n.parse().expect("invalid number of players"))
+                .unwrap_or(2),
+        },
+        // Default to a local SyncTest session so `cargo run` keeps working
+        // without any arguments, just re-simulating against itself.
+        _ => NetArgs::SyncTest { num_players: 2 },
+    }
+}
 
 fn main() {
-    App::new()
+    let mut session_builder = SessionBuilder::<GgrsConfig>::new()
+        .with_num_players(2)
+        .with_max_prediction_window(MAX_PREDICTION_WINDOW)
+        .expect("invalid max prediction window")
+        .with_input_delay(INPUT_DELAY)
+        .with_fps(FPS)
+        .expect("invalid fps");
+
+    let mut app = App::new();
+    app
         // Insert resources first
         .insert_resource(GameState::default())
         .insert_resource(Score(0))
+        .insert_resource(LevelBounds::default())
         .insert_resource(ClearColor(BACKGROUND_COLOR))
-
+        // `RapierConfiguration` is `FromWorld`, not `Default`, so start from
+        // its own constructor and override just the gravity.
+        .insert_resource(RapierConfiguration {
+            gravity: Vec2::ZERO,
+            ..RapierConfiguration::new(1.0)
+        })
         // Add plugins after inserting resources
         .add_plugins(DefaultPlugins)
         .add_plugins(
             stepping::SteppingPlugin::default()
                 .add_schedule(Update)
-                .add_schedule(FixedUpdate)
+                .add_schedule(GgrsSchedule)
                 .at(Val::Percent(35.0), Val::Percent(50.0)),
         )
-
-        // Register events
-        .add_event::<CollisionEvent>()
-
+        .add_plugins(GgrsPlugin::<GgrsConfig>::default())
+        // Physics steps alongside our own gameplay systems inside `GgrsSchedule`,
+        // so rapier re-simulates exactly like everything else on a rollback.
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_schedule(GgrsSchedule))
+        .init_asset::<LevelAsset>()
+        .init_asset_loader::<LevelAssetLoader>()
+        // Every piece of state that must survive a rollback (save/restore on
+        // misprediction) is registered here rather than added to a schedule.
+        // NOTE: `RapierContext` (broad/narrow-phase caches, island manager,
+        // sleeping/CCD flags) isn't `Clone` and so can't be snapshotted here;
+        // it's rebuilt deterministically each resimulated frame from the
+        // restored `Transform`/`Velocity`/`Collider` state instead, which is
+        // sufficient since none of our colliders sleep (`GravityScale(0.0)`,
+        // always-moving ball) or depend on cross-frame contact history.
+        .rollback_component_with_clone::<Transform>()
+        .rollback_component_with_copy::<Velocity>()
+        .rollback_component_with_copy::<Brick>()
+        .rollback_resource_with_copy::<Score>()
+        .rollback_resource_with_copy::<LevelBounds>()
+        .rollback_resource_with_clone::<GameState>()
         // Add systems
         .add_systems(Startup, setup)
-        // Add our gameplay simulation systems to the fixed timestep schedule
-        // which runs at 64 Hz by default
+        .add_systems(bevy_ggrs::ReadInputs, read_local_inputs)
+        // Our gameplay simulation now lives in `GgrsSchedule`, which GGRS
+        // drives at a fixed 60 Hz and re-runs wholesale on misprediction.
         .add_systems(
-            FixedUpdate,
-            (
-                apply_velocity,
-                move_paddle,
-                check_for_collisions,
-                play_collision_sound,
-            )
-                // `chain`ing systems together runs them in order
-                .chain(),
+            GgrsSchedule,
+            (move_paddle, check_for_collisions, play_collision_sound).chain(),
         )
-        .add_systems(Update, update_scoreboard)
-        .run();
+        // By the time the session is inserted below, every level handle is
+        // already confirmed `Loaded` (see the pump loop further down), so
+        // polling here in `Update` rather than `GgrsSchedule` no longer risks
+        // peers disagreeing about which colliders exist on a given frame.
+        .add_systems(Update, (update_scoreboard, spawn_pending_level))
+        // The camera is presentation, not gameplay state, so it tracks the
+        // ball in bevy's own `FixedUpdate` rather than `GgrsSchedule`.
+        .add_systems(FixedUpdate, camera_follow);
+
+    // Which colliders exist (walls/bricks/slopes) feeds straight into
+    // `GgrsSchedule`'s collision results, so two peers must never disagree
+    // about what's loaded on a given frame number. Rather than let
+    // `spawn_pending_level` race wall-clock asset I/O against the rollback
+    // schedule, pump `Update` (which runs `setup` once, then drives the
+    // asset server's background loads) until every discovered level has
+    // finished loading, and only then hand off to `GgrsPlugin` by inserting
+    // the `Session` - before that, there's no session for it to step, so
+    // `GgrsSchedule` never runs.
+    loop {
+        app.update();
+        let world = app.world();
+        let game_state = world.resource::<GameState>();
+        let asset_server = world.resource::<AssetServer>();
+
+        if game_state.levels.is_empty() {
+            eprintln!(
+                "no levels found in `assets/levels/` - add at least one `.level` file and try again"
+            );
+            std::process::exit(1);
+        }
+        if let Some(path) = game_state.levels.iter().find_map(|handle| {
+            matches!(
+                asset_server.load_state(handle),
+                bevy::asset::LoadState::Failed(_)
+            )
+            .then(|| asset_server.get_path(handle.id()))
+            .flatten()
+        }) {
+            eprintln!("failed to load level asset {path}");
+            std::process::exit(1);
+        }
+
+        let all_loaded = game_state
+            .levels
+            .iter()
+            .all(|handle| asset_server.load_state(handle) == bevy::asset::LoadState::Loaded);
+        if all_loaded {
+            break;
+        }
+    }
+
+    match parse_net_args() {
+        NetArgs::SyncTest { num_players } => {
+            for i in 0..num_players {
+                session_builder = session_builder
+                    .add_player(PlayerType::Local, i)
+                    .expect("failed to add local player");
+            }
+            let session = session_builder
+                .start_synctest_session()
+                .expect("failed to start synctest session");
+            app.insert_resource(Session::SyncTest(session));
+        }
+        NetArgs::Spectate { host_addr } => {
+            let socket = bevy_ggrs::ggrs::UdpNonBlockingSocket::bind_to_port(0)
+                .expect("failed to bind spectator socket");
+            let session = session_builder
+                .start_spectator_session(host_addr, socket);
+            app.insert_resource(Session::Spectator(session));
+        }
+        NetArgs::P2P {
+            local_port,
+            remote_addr,
+        } => {
+            let socket = bevy_ggrs::ggrs::UdpNonBlockingSocket::bind_to_port(local_port)
+                .expect("failed to bind P2P socket");
+            session_builder = session_builder
+                .add_player(PlayerType::Local, 0)
+                .expect("failed to add local player")
+                .add_player(PlayerType::Remote(remote_addr), 1)
+                .expect("failed to add remote player");
+            let session = session_builder
+                .start_p2p_session(socket)
+                .expect("failed to start P2P session");
+            app.insert_resource(Session::P2P(session));
+        }
+    }
+
+    app.run();
 }
 
 #[derive(Component)]
-struct Paddle;
+struct Paddle {
+    handle: usize,
+}
 
 #[derive(Component)]
 struct Ball;
 
-#[derive(Component, Deref, DerefMut)]
-struct Velocity(Vec2);
-
-#[derive(Component)]
-struct Collider;
+#[derive(Component, Clone, Copy)]
+struct Brick {
+    hit_points: u32,
+    /// `hit_points` this brick started with, kept around so its crack tint
+    /// can be derived fresh each frame instead of accumulated in place.
+    max_hit_points: u32,
+    score_value: usize,
+    base_color: Color,
+}
 
-#[derive(Event, Default)]
-struct CollisionEvent;
+impl Brick {
+    /// The sprite color a brick should show for its current `hit_points`,
+    /// computed fresh from `base_color` rather than mutated in place, so it
+    /// stays correct across a GGRS resimulation: `hit_points` rolls back
+    /// because `Brick` is rollback-tracked, but a `Sprite.color` that was
+    /// repeatedly `.mix()`-ed in `check_for_collisions` would not - it would
+    /// keep compounding on every resimulated frame regardless of the
+    /// rolled-back hit count.
+    fn tint(&self) -> Color {
+        if self.max_hit_points <= 1 || self.hit_points >= self.max_hit_points {
+            return self.base_color;
+        }
+        let hits_taken = self.max_hit_points - self.hit_points;
+        let factor = (hits_taken as f32 * CRACK_TINT_FACTOR).min(1.0);
+        self.base_color.mix(&CRACKED_BRICK_COLOR, factor)
+    }
+}
 
+/// Marks a brick that reflects the ball but never takes damage; excluded
+/// from both despawning and the `all_bricks_are_cleared` check.
 #[derive(Component)]
-struct Brick;
+struct Indestructible;
+
+/// How far a brick's sprite tint lerps toward `CRACKED_BRICK_COLOR` on each
+/// non-fatal hit.
+const CRACK_TINT_FACTOR: f32 = 0.35;
+const CRACKED_BRICK_COLOR: Color = Color::srgb(0.25, 0.25, 0.3);
+
+const SLOPE_COLOR: Color = Color::srgb(0.9, 0.7, 0.3);
+const SLOPE_LENGTH: f32 = 120.0;
+const SLOPE_THICKNESS: f32 = 10.0;
+
+/// An angled deflector surface. Rapier's contact solver already reflects a
+/// dynamic body off any collider shape at the correct angle, so `normal` is
+/// kept mainly for spawning (orienting the collider) and for anything that
+/// wants to reason about the surface directly; the bounce itself -
+/// `v' = v - 2 * (v · n) * n` - falls out of the physics engine for free.
+#[derive(Component)]
+struct Slope {
+    #[allow(dead_code)]
+    normal: Vec2,
+}
+
+/// Spawns a fixed, angled bumper whose long edge is tangent to `normal` at
+/// `position`, with the same elastic/frictionless material as the ball so it
+/// redirects play diagonally instead of absorbing it.
+fn spawn_slope(commands: &mut Commands, position: Vec2, normal: Vec2) {
+    let normal = normal.normalize();
+    // Orient the cuboid's local +X edge along the surface tangent (the
+    // normal rotated -90°) so the collider's face matches `normal`.
+    let tangent = Vec2::new(normal.y, -normal.x);
+    let rotation = Quat::from_rotation_z(tangent.y.atan2(tangent.x));
+
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: SLOPE_COLOR,
+                    ..default()
+                },
+                transform: Transform {
+                    translation: position.extend(0.0),
+                    rotation,
+                    scale: Vec3::new(SLOPE_LENGTH, SLOPE_THICKNESS, 1.0),
+                },
+                ..default()
+            },
+            Slope { normal },
+            RigidBody::Fixed,
+            Collider::cuboid(0.5, 0.5),
+            Restitution {
+                coefficient: 1.0,
+                combine_rule: CoefficientCombineRule::Max,
+            },
+            Friction::coefficient(0.0),
+        ))
+        // `next_level` (which triggers the respawn in `spawn_pending_level`)
+        // can itself be speculatively predicted and rolled back, so this
+        // bumper's existence/position must be rollback-tracked like the
+        // bricks, or a misprediction would leave it permanently out of sync
+        // with the corrected `LevelBounds`.
+        .add_rollback();
+}
 
 #[derive(Resource, Deref)]
 struct CollisionSound(Handle<AudioSource>);
 
+/// Marks the four arena-boundary walls so they can be despawned and
+/// rebuilt when a new level's `LevelBounds` differ from the previous one.
+#[derive(Component)]
+struct Wall;
+
 // This bundle is a collection of the components that define a "wall" in our game
 #[derive(Bundle)]
 struct WallBundle {
     // You can nest bundles inside of other bundles like this
     // Allowing you to compose their functionality
+    marker: Wall,
     sprite_bundle: SpriteBundle,
+    rigid_body: RigidBody,
     collider: Collider,
 }
 
@@ -184,20 +504,20 @@ enum WallLocation {
 
 impl WallLocation {
     /// Location of the *center* of the wall, used in `transform.translation()`
-    fn position(&self) -> Vec2 {
+    fn position(&self, bounds: &LevelBounds) -> Vec2 {
         match self {
-            WallLocation::Left => Vec2::new(LEFT_WALL, 0.),
-            WallLocation::Right => Vec2::new(RIGHT_WALL, 0.),
-            WallLocation::Bottom => Vec2::new(0., BOTTOM_WALL),
-            WallLocation::Top => Vec2::new(0., TOP_WALL),
+            WallLocation::Left => Vec2::new(bounds.left, 0.),
+            WallLocation::Right => Vec2::new(bounds.right, 0.),
+            WallLocation::Bottom => Vec2::new(0., bounds.bottom),
+            WallLocation::Top => Vec2::new(0., bounds.top),
         }
     }
 
     /// (x, y) dimensions of the wall, used in `transform.scale()`
-    fn size(&self) -> Vec2 {
-        let arena_height = TOP_WALL - BOTTOM_WALL;
-        let arena_width = RIGHT_WALL - LEFT_WALL;
-        // Make sure we haven't messed up our constants
+    fn size(&self, bounds: &LevelBounds) -> Vec2 {
+        let arena_height = bounds.height();
+        let arena_width = bounds.width();
+        // Make sure the level's arena size hasn't been misconfigured
         assert!(arena_height > 0.0);
         assert!(arena_width > 0.0);
 
@@ -215,17 +535,18 @@ impl WallLocation {
 impl WallBundle {
     // This "builder method" allows us to reuse logic across our wall entities,
     // making our code easier to read and less prone to bugs when we change the logic
-    fn new(location: WallLocation) -> WallBundle {
+    fn new(location: WallLocation, bounds: &LevelBounds) -> WallBundle {
         WallBundle {
+            marker: Wall,
             sprite_bundle: SpriteBundle {
                 transform: Transform {
                     // We need to convert our Vec2 into a Vec3, by giving it a z-coordinate
                     // This is used to determine the order of our sprites
-                    translation: location.position().extend(0.0),
+                    translation: location.position(bounds).extend(0.0),
                     // The z-scale of 2D objects must always be 1.0,
                     // or their ordering will be affected in surprising ways.
                     // See https://github.com/bevyengine/bevy/issues/4149
-                    scale: location.size().extend(1.0),
+                    scale: location.size(bounds).extend(1.0),
                     ..default()
                 },
                 sprite: Sprite {
@@ -234,22 +555,25 @@ impl WallBundle {
                 },
                 ..default()
             },
-            collider: Collider,
+            rigid_body: RigidBody::Fixed,
+            // The sprite's `Transform.scale` already carries the wall's full
+            // size (see above), and rapier scales collider shapes by it, so
+            // a unit cuboid here ends up matching the rendered quad exactly.
+            collider: Collider::cuboid(0.5, 0.5),
         }
     }
 }
 
 // This resource tracks the game's score
-#[derive(Resource, Deref, DerefMut)]
+#[derive(Resource, Deref, DerefMut, Clone, Copy, Default)]
 struct Score(usize);
 
 #[derive(Component)]
 struct ScoreboardUi;
 
-fn load_level(level: &Level, commands: &mut Commands) {
+fn load_level(level: &LevelAsset, commands: &mut Commands, bounds: &LevelBounds) {
     // Determine the number of bricks per row and total rows
     let bricks_per_row = level.brick_layout[0].len();
-    let total_rows = level.brick_layout.len();
 
     // Calculate total width of a row: (brick width * number of bricks) + (gap * (number of bricks - 1))
     let total_width = bricks_per_row as f32 * BRICK_SIZE.x + (bricks_per_row as f32 - 1.) * GAP_BETWEEN_BRICKS;
@@ -257,38 +581,115 @@ fn load_level(level: &Level, commands: &mut Commands) {
     // Starting x position to center bricks horizontally
     let start_x = -total_width / 2. + BRICK_SIZE.x / 2.;
 
-    // Starting y position near the top wall
-    let start_y = TOP_WALL - GAP_BETWEEN_BRICKS_AND_CEILING - BRICK_SIZE.y / 2.;
+    // Starting y position near the top wall, wherever the level's bounds put it
+    let start_y = bounds.top - GAP_BETWEEN_BRICKS_AND_CEILING - BRICK_SIZE.y / 2.;
 
     for (row_idx, row) in level.brick_layout.iter().enumerate() {
-        for (brick_idx, brick) in row.iter().enumerate() {
-            if brick.is_some() {
-                // Calculate brick position
-                let x = start_x + brick_idx as f32 * (BRICK_SIZE.x + GAP_BETWEEN_BRICKS);
-                let y = start_y - row_idx as f32 * (BRICK_SIZE.y + GAP_BETWEEN_BRICKS);
-
-                // Spawn brick with Collider
-                commands.spawn(SpriteBundle {
-                    sprite: Sprite {
-                        color: BRICK_COLOR,
-                        ..Default::default()
-                    },
-                    transform: Transform {
-                        translation: Vec3::new(x, y, 0.0),
-                        scale: Vec3::new(BRICK_SIZE.x, BRICK_SIZE.y, 1.0),
+        for (brick_idx, cell) in row.iter().enumerate() {
+            let x = start_x + brick_idx as f32 * (BRICK_SIZE.x + GAP_BETWEEN_BRICKS);
+            let y = start_y - row_idx as f32 * (BRICK_SIZE.y + GAP_BETWEEN_BRICKS);
+
+            match cell {
+                GridCell::Empty => {}
+                GridCell::Brick(cell) => {
+                    // Spawn brick as a fixed rapier body, registered with the rollback
+                    // system so the spawn/despawn itself is resimulated identically on
+                    // every peer.
+                    let mut entity = commands.spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: cell.color,
+                            ..Default::default()
+                        },
+                        transform: Transform {
+                            translation: Vec3::new(x, y, 0.0),
+                            scale: Vec3::new(BRICK_SIZE.x, BRICK_SIZE.y, 1.0),
+                            ..Default::default()
+                        },
                         ..Default::default()
-                    },
-                    ..Default::default()
-                })
-                  .insert(Brick)
-                  .insert(Collider); // Ensure Collider is added
+                    });
+                    entity
+                        .insert(Brick {
+                            hit_points: cell.hit_points,
+                            max_hit_points: cell.hit_points,
+                            score_value: cell.score_value,
+                            base_color: cell.color,
+                        })
+                        .insert(RigidBody::Fixed)
+                        .insert(Collider::cuboid(0.5, 0.5))
+                        .insert(ActiveEvents::COLLISION_EVENTS)
+                        .add_rollback();
+
+                    if cell.indestructible {
+                        entity.insert(Indestructible);
+                    }
+                }
+                GridCell::Slope(slope) => {
+                    spawn_slope(commands, Vec2::new(x, y), slope.normal);
+                }
             }
         }
     }
 }
 
+/// Spawns `game_state.current_level`'s walls, bumpers and bricks as soon as
+/// its `LevelAsset` handle resolves. A no-op once `level_spawned` is set,
+/// until `next_level` clears it again.
+fn spawn_pending_level(
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    mut level_bounds: ResMut<LevelBounds>,
+    level_assets: Res<Assets<LevelAsset>>,
+    arena_furniture: Query<Entity, Or<(With<Wall>, With<Slope>)>>,
+    mut paddles: Query<&mut Transform, With<Paddle>>,
+) {
+    if game_state.level_spawned {
+        return;
+    }
+    let Some(handle) = game_state.levels.get(game_state.current_level) else {
+        return;
+    };
+    let Some(level) = level_assets.get(handle) else {
+        return;
+    };
+
+    // The previous level's walls and bumpers were sized for its own
+    // `arena_size`; clear them before rebuilding at the new bounds.
+    for entity in &arena_furniture {
+        commands.entity(entity).despawn();
+    }
 
+    *level_bounds = LevelBounds::from_arena_size(level.arena_size);
+
+    // Like the bricks, these walls are spawned in response to `next_level`,
+    // which runs inside `GgrsSchedule` (on rollback-tracked `GameState`), so
+    // a misprediction can unwind this spawn entirely; without `.add_rollback()`
+    // they'd survive a rollback that undoes the level transition that created
+    // them, permanently desyncing the visible/collidable arena bounds.
+    commands
+        .spawn(WallBundle::new(WallLocation::Left, &level_bounds))
+        .add_rollback();
+    commands
+        .spawn(WallBundle::new(WallLocation::Right, &level_bounds))
+        .add_rollback();
+    commands
+        .spawn(WallBundle::new(WallLocation::Bottom, &level_bounds))
+        .add_rollback();
+    commands
+        .spawn(WallBundle::new(WallLocation::Top, &level_bounds))
+        .add_rollback();
+
+    // A couple of angled bumpers flanking the paddle, to redirect the ball
+    // diagonally instead of straight back up.
+    let paddle_y = level_bounds.bottom + GAP_BETWEEN_PADDLE_AND_FLOOR;
+    for mut paddle_transform in &mut paddles {
+        paddle_transform.translation.y = paddle_y;
+    }
+    spawn_slope(&mut commands, Vec2::new(-200.0, paddle_y + 80.0), Vec2::new(-1.0, 1.0));
+    spawn_slope(&mut commands, Vec2::new(200.0, paddle_y + 80.0), Vec2::new(1.0, 1.0));
 
+    load_level(level, &mut commands, &level_bounds);
+    game_state.level_spawned = true;
+}
 
 // Add the game's entities to our world
 use bevy::prelude::*;
@@ -301,8 +702,11 @@ fn setup(
     mut game_state: ResMut<GameState>,
     mut materials: ResMut<Assets<ColorMaterial>>, // Added parameter
 ) {
-    // Initialize the game state
+    // Discover and start loading every level file; bricks for the initial
+    // level are spawned by `spawn_pending_level` once it resolves.
+    game_state.levels = discover_levels(&asset_server);
     game_state.current_level = 0;
+    game_state.level_spawned = false;
 
     // Camera
     commands.spawn(Camera2dBundle::default());
@@ -311,37 +715,61 @@ fn setup(
     let ball_collision_sound = asset_server.load("sounds/breakout_collision.ogg");
     commands.insert_resource(CollisionSound(ball_collision_sound));
 
-    // Paddle
-    let paddle_y = BOTTOM_WALL + GAP_BETWEEN_PADDLE_AND_FLOOR;
-    commands.spawn((
-        SpriteBundle {
-            transform: Transform {
-                translation: Vec3::new(0.0, paddle_y, 0.0),
-                scale: PADDLE_SIZE.extend(1.0),
+    // Paddle, one per player handle so `move_paddle` can tell the two peers apart.
+    // Walls haven't been spawned yet (they come from the level, once its
+    // handle resolves in `spawn_pending_level`), so seed the paddle's height
+    // off the default bounds; `spawn_pending_level` moves it to each level's
+    // own floor, and `move_paddle`'s x-clamp tracks the level's `LevelBounds`.
+    let paddle_y = LevelBounds::default().bottom + GAP_BETWEEN_PADDLE_AND_FLOOR;
+    for handle in 0..2 {
+        commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(0.0, paddle_y, 0.0),
+                        scale: PADDLE_SIZE.extend(1.0),
+                        ..default()
+                    },
+                    sprite: Sprite {
+                        color: PADDLE_COLOR,
+                        ..default()
+                    },
+                    ..default()
+                },
+                Paddle { handle },
+                RigidBody::Fixed,
+                Collider::cuboid(0.5, 0.5),
+            ))
+            .add_rollback();
+    }
+
+    // Ball with ColorMaterial. `RigidBody::Dynamic` plus a ball collider with
+    // high restitution, zero friction/gravity and CCD gives correct elastic,
+    // multi-contact bounces (e.g. wedged between two bricks) that the old
+    // single-axis reflection couldn't handle.
+    commands
+        .spawn((
+            MaterialMesh2dBundle::<ColorMaterial> { // Specified ColorMaterial
+                mesh: meshes.add(Mesh::from(Circle::new(BALL_DIAMETER / 2.0))).into(),
+                material: materials.add(ColorMaterial::from(BALL_COLOR)), // Assigned material
+                transform: Transform::from_translation(BALL_STARTING_POSITION)
+                  .with_scale(Vec3::new(1.0, 1.0, 1.0)),
                 ..default()
             },
-            sprite: Sprite {
-                color: PADDLE_COLOR,
-                ..default()
+            Ball,
+            RigidBody::Dynamic,
+            Collider::ball(BALL_DIAMETER / 2.0),
+            Velocity::linear(INITIAL_BALL_DIRECTION.normalize() * BALL_SPEED),
+            Restitution {
+                coefficient: 1.0,
+                combine_rule: CoefficientCombineRule::Max,
             },
-            ..default()
-        },
-        Paddle,
-        Collider,
-    ));
-
-    // Ball with ColorMaterial
-    commands.spawn((
-        MaterialMesh2dBundle::<ColorMaterial> { // Specified ColorMaterial
-            mesh: meshes.add(Mesh::from(Circle::new(BALL_DIAMETER / 2.0))).into(),
-            material: materials.add(ColorMaterial::from(BALL_COLOR)), // Assigned material
-            transform: Transform::from_translation(BALL_STARTING_POSITION)
-              .with_scale(Vec3::new(1.0, 1.0, 1.0)),
-            ..default()
-        },
-        Ball,
-        Velocity(INITIAL_BALL_DIRECTION.normalize() * BALL_SPEED),
-    ));
+            Friction::coefficient(0.0),
+            GravityScale(0.0),
+            Ccd::enabled(),
+            ActiveEvents::COLLISION_EVENTS,
+        ))
+        .add_rollback();
 
     // Scoreboard
     commands.spawn((
@@ -372,52 +800,103 @@ fn setup(
           }),
     ));
 
-    // Walls
-    commands.spawn(WallBundle::new(WallLocation::Left));
-    commands.spawn(WallBundle::new(WallLocation::Right));
-    commands.spawn(WallBundle::new(WallLocation::Bottom));
-    commands.spawn(WallBundle::new(WallLocation::Top));
-
-    // Bricks for the initial level
-    load_level(&game_state.levels[game_state.current_level], &mut commands);
+    // Walls and bumpers are level-sized, so `spawn_pending_level` builds them
+    // alongside the bricks once the first level's handle resolves.
 }
 
+/// Reads this peer's local keyboard state into a `BoxInput` and hands it to
+/// GGRS every frame, ahead of the deterministic simulation step.
+fn read_local_inputs(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    local_players: Res<LocalPlayers>,
+) {
+    let mut local_inputs = bevy::utils::HashMap::new();
 
+    for handle in &local_players.0 {
+        let mut inp: u8 = 0;
 
+        if keyboard_input.pressed(KeyCode::ArrowLeft) {
+            inp |= INPUT_LEFT;
+        }
+        if keyboard_input.pressed(KeyCode::ArrowRight) {
+            inp |= INPUT_RIGHT;
+        }
+
+        local_inputs.insert(*handle, BoxInput { inp });
+    }
+
+    commands.insert_resource(LocalInputs::<GgrsConfig>(local_inputs));
+}
 
 fn move_paddle(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut query: Query<&mut Transform, With<Paddle>>,
-    time: Res<Time>,
+    inputs: Res<PlayerInputs<GgrsConfig>>,
+    bounds: Res<LevelBounds>,
+    mut query: Query<(&mut Transform, &Paddle)>,
 ) {
-    let mut paddle_transform = query.single_mut();
-    let mut direction = 0.0;
+    for (mut paddle_transform, paddle) in &mut query {
+        let (input, _) = inputs[paddle.handle];
+        let mut direction = 0.0;
 
-    if keyboard_input.pressed(KeyCode::ArrowLeft) {
-        direction -= 1.0;
-    }
+        if input.inp & INPUT_LEFT != 0 {
+            direction -= 1.0;
+        }
+        if input.inp & INPUT_RIGHT != 0 {
+            direction += 1.0;
+        }
+
+        // Rollback state must be a pure function of (previous state, input),
+        // so movement uses the fixed `DELTA_TIME` instead of `Time::delta_seconds()`.
+        let new_paddle_position =
+            paddle_transform.translation.x + direction * PADDLE_SPEED * DELTA_TIME;
 
-    if keyboard_input.pressed(KeyCode::ArrowRight) {
-        direction += 1.0;
+        // Update the paddle position, making sure it doesn't cause the
+        // paddle to leave the current level's arena.
+        let left_bound = bounds.left + WALL_THICKNESS / 2.0 + PADDLE_SIZE.x / 2.0 + PADDLE_PADDING;
+        let right_bound = bounds.right - WALL_THICKNESS / 2.0 - PADDLE_SIZE.x / 2.0 - PADDLE_PADDING;
+
+        paddle_transform.translation.x = new_paddle_position.clamp(left_bound, right_bound);
     }
+}
 
-    // Calculate the new horizontal paddle position based on player input
-    let new_paddle_position =
-        paddle_transform.translation.x + direction * PADDLE_SPEED * time.delta_seconds();
+/// Smoothly tracks the ball with the camera, clamped to the current level's
+/// `LevelBounds` so the view never shows past the outer walls. On an axis
+/// where the level is narrower than the viewport, the camera centers on that
+/// axis instead of clamping. Purely a presentation concern - the camera
+/// isn't part of gameplay state - so unlike `move_paddle` it runs in the
+/// regular `FixedUpdate` schedule rather than `GgrsSchedule`, and is free to
+/// use wall-clock `Time` for its smoothing.
+fn camera_follow(
+    time: Res<Time>,
+    bounds: Res<LevelBounds>,
+    ball_query: Query<&Transform, With<Ball>>,
+    mut camera_query: Query<&mut Transform, (With<Camera2d>, Without<Ball>)>,
+) {
+    let Ok(ball_transform) = ball_query.get_single() else {
+        return;
+    };
+    let Ok(mut camera_transform) = camera_query.get_single_mut() else {
+        return;
+    };
 
-    // Update the paddle position,
-    // making sure it doesn't cause the paddle to leave the arena
-    let left_bound = LEFT_WALL + WALL_THICKNESS / 2.0 + PADDLE_SIZE.x / 2.0 + PADDLE_PADDING;
-    let right_bound = RIGHT_WALL - WALL_THICKNESS / 2.0 - PADDLE_SIZE.x / 2.0 - PADDLE_PADDING;
+    let target = ball_transform.translation.truncate();
+    let current = camera_transform.translation.truncate();
+    let lerp_factor = (CAMERA_FOLLOW_SPEED * time.delta_seconds()).min(1.0);
+    let tracked = current.lerp(target, lerp_factor);
 
-    paddle_transform.translation.x = new_paddle_position.clamp(left_bound, right_bound);
-}
+    let half_viewport = VIEWPORT_SIZE / 2.0;
+    let x = if bounds.width() <= VIEWPORT_SIZE.x {
+        0.0
+    } else {
+        tracked.x.clamp(bounds.left + half_viewport.x, bounds.right - half_viewport.x)
+    };
+    let y = if bounds.height() <= VIEWPORT_SIZE.y {
+        0.0
+    } else {
+        tracked.y.clamp(bounds.bottom + half_viewport.y, bounds.top - half_viewport.y)
+    };
 
-fn apply_velocity(mut query: Query<(&mut Transform, &Velocity)>, time: Res<Time>) {
-    for (mut transform, velocity) in &mut query {
-        transform.translation.x += velocity.x * time.delta_seconds();
-        transform.translation.y += velocity.y * time.delta_seconds();
-    }
+    camera_transform.translation = Vec3::new(x, y, camera_transform.translation.z);
 }
 
 fn update_scoreboard(score: Res<Score>, mut query: Query<&mut Text, With<ScoreboardUi>>) {
@@ -428,67 +907,42 @@ fn update_scoreboard(score: Res<Score>, mut query: Query<&mut Text, With<Scorebo
 fn check_for_collisions(
     mut commands: Commands,
     mut score: ResMut<Score>,
-    mut ball_query: Query<(&mut Velocity, &Transform), With<Ball>>,
-    collider_query: Query<(Entity, &Transform, Option<&Brick>), With<Collider>>,
-    mut collision_events: EventWriter<CollisionEvent>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut destructible_bricks: Query<(&mut Brick, &mut Sprite), Without<Indestructible>>,
+    clearable_bricks: Query<Entity, (With<Brick>, Without<Indestructible>)>,
     mut game_state: ResMut<GameState>,
-    brick_query: Query<Entity, With<Brick>>,
 ) {
-    let (mut ball_velocity, ball_transform) = ball_query.single_mut();
-
-    for (collider_entity, collider_transform, maybe_brick) in &collider_query {
-        let collision = ball_collision(
-            BoundingCircle::new(ball_transform.translation.truncate(), BALL_DIAMETER / 2.),
-            Aabb2d::new(
-                collider_transform.translation.truncate(),
-                collider_transform.scale.truncate() / 2.,
-            ),
-        );
-
-        if let Some(collision) = collision {
-            println!("Collision detected with Entity: {:?}", collider_entity);
-            // Sends a collision event so that other systems can react to the collision
-            collision_events.send_default();
-
-            // Bricks should be despawned and increment the scoreboard on collision
-            if maybe_brick.is_some() {
-                println!("Brick hit! Despawning brick: {:?}", collider_entity);
-                commands.entity(collider_entity).despawn();
-                **score += 1;
-            }
-
-            // Reflect the ball's velocity when it collides
-            let mut reflect_x = false;
-            let mut reflect_y = false;
-
-            // Reflect only if the velocity is in the opposite direction of the collision
-            // This prevents the ball from getting stuck inside the bar
-            match collision {
-                Collision::Left => reflect_x = ball_velocity.x > 0.0,
-                Collision::Right => reflect_x = ball_velocity.x < 0.0,
-                Collision::Top => reflect_y = ball_velocity.y < 0.0,
-                Collision::Bottom => reflect_y = ball_velocity.y > 0.0,
-            }
-
-            // Reflect velocity on the x-axis if we hit something on the x-axis
-            if reflect_x {
-                println!("Reflecting ball velocity on the X-axis");
-                ball_velocity.x = -ball_velocity.x;
-            }
-
-            // Reflect velocity on the y-axis if we hit something on the y-axis
-            if reflect_y {
-                println!("Reflecting ball velocity on the Y-axis");
-                ball_velocity.y = -ball_velocity.y;
+    // Ball-on-brick reflection and elastic bouncing are now rapier's job
+    // (see the `Restitution`/`Ccd` setup on the ball); we just watch the
+    // collision stream it produces to apply damage and award points.
+    // `Indestructible` bricks still reflect the ball physically, but never
+    // take damage, so they're simply absent from `destructible_bricks`.
+    for event in collision_events.read() {
+        if let CollisionEvent::Started(a, b, _flags) = event {
+            for entity in [a, b] {
+                let Ok((mut brick, mut sprite)) = destructible_bricks.get_mut(*entity) else {
+                    continue;
+                };
+
+                brick.hit_points = brick.hit_points.saturating_sub(1);
+                if brick.hit_points == 0 {
+                    commands.entity(*entity).despawn();
+                    **score += brick.score_value;
+                } else {
+                    // Derived fresh from `hit_points` every time rather than
+                    // mutated in place, so it's correct even when this frame
+                    // is a GGRS resimulation after a misprediction.
+                    sprite.color = brick.tint();
+                }
             }
         }
     }
 
-    if all_bricks_are_cleared(brick_query) {
+    if all_bricks_are_cleared(clearable_bricks) {
         if game_state.current_level + 1 < game_state.levels.len() {
-            println!("All bricks cleared! Proceeding to next level.");
+            // `spawn_pending_level` (running in `Update`) picks this up and
+            // spawns the next level's bricks once its asset handle resolves.
             next_level(&mut game_state);
-            load_level(&game_state.levels[game_state.current_level], &mut commands);
         } else {
             println!("You have completed all levels!");
             // Optionally, trigger a victory screen or reset the game
@@ -496,10 +950,6 @@ fn check_for_collisions(
     }
 }
 
-
-
-
-
 fn despawn_bricks(
     mut commands: Commands,
     brick_query: Query<Entity, With<Brick>>,
@@ -509,22 +959,21 @@ fn despawn_bricks(
     }
 }
 
-
-// Check if the level vector is empty
-fn all_bricks_are_cleared(brick_query: Query<Entity, With<Brick>>) -> bool {
-    brick_query.is_empty()
+// Check if every destructible brick has been cleared. Indestructible bricks
+// are excluded, since a level made entirely of bumpers would otherwise never
+// "complete".
+fn all_bricks_are_cleared(clearable_bricks: Query<Entity, (With<Brick>, Without<Indestructible>)>) -> bool {
+    clearable_bricks.is_empty()
 }
 
-
 fn play_collision_sound(
     mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
     sound: Res<CollisionSound>,
 ) {
-    // Play a sound once per frame if a collision occurred.
-    if !collision_events.is_empty() {
-        // This prevents events staying active on the next frame.
-        collision_events.clear();
+    // Play a sound once per frame if any collision started, from the same
+    // rapier event stream `check_for_collisions` reads for scoring.
+    if collision_events.read().any(|event| matches!(event, CollisionEvent::Started(..))) {
         commands.spawn(AudioBundle {
             source: sound.clone(),
             // auto-despawn the entity when playback finishes
@@ -532,35 +981,3 @@ fn play_collision_sound(
         });
     }
 }
-
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum Collision {
-    Left,
-    Right,
-    Top,
-    Bottom,
-}
-
-// Returns `Some` if `ball` collides with `bounding_box`.
-// The returned `Collision` is the side of `bounding_box` that `ball` hit.
-fn ball_collision(ball: BoundingCircle, bounding_box: Aabb2d) -> Option<Collision> {
-    if !ball.intersects(&bounding_box) {
-        return None;
-    }
-
-    let closest = bounding_box.closest_point(ball.center());
-    let offset = ball.center() - closest;
-    let side = if offset.x.abs() > offset.y.abs() {
-        if offset.x < 0. {
-            Collision::Left
-        } else {
-            Collision::Right
-        }
-    } else if offset.y > 0. {
-        Collision::Top
-    } else {
-        Collision::Bottom
-    };
-
-    Some(side)
-}
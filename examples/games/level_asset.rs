@@ -0,0 +1,193 @@
+//! A custom asset format and loader for breakout level layouts: a simple
+//! ASCII grid where each whitespace-separated token is one cell, read from files under
+//! `assets/levels/`.
+//!
+//! ```text
+//! . . 1 .
+//! 2 2 2 2
+//! # # # #
+//! ```
+//!
+//! `.` is an empty cell, a digit `1-9` is a brick that takes that many hits
+//! to clear, and `#` is an indestructible brick that reflects the ball but
+//! never clears. `/` and `\` are 45° angled bumpers (see [`SlopeCell`]) that
+//! redirect the ball diagonally instead of clearing. A cell may optionally be
+//! suffixed with `:RRGGBB` to override its color, e.g. `3:ff8800`.
+//!
+//! The grid may optionally be preceded by a single `WIDTHxHEIGHT` line (e.g.
+//! `1200x2000`) giving the arena's size in world units, letting a level be
+//! larger than the viewport; levels that omit it get [`DEFAULT_ARENA_SIZE`].
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use std::io::{BufRead, BufReader};
+
+/// One cell of a parsed level grid.
+#[derive(Clone, Copy)]
+pub enum GridCell {
+    Empty,
+    Brick(BrickCell),
+    Slope(SlopeCell),
+}
+
+#[derive(Clone, Copy)]
+pub struct BrickCell {
+    pub hit_points: u32,
+    pub score_value: usize,
+    pub indestructible: bool,
+    pub color: Color,
+}
+
+/// An angled deflector; `normal` is the unit surface normal the ball
+/// reflects off, e.g. `Vec2::new(-1.0, 1.0).normalize()` for a `/` bumper.
+#[derive(Clone, Copy)]
+pub struct SlopeCell {
+    pub normal: Vec2,
+}
+
+/// The arena size assumed for levels that don't specify their own
+/// `WIDTHxHEIGHT` header, matching the original fixed screen bounds.
+pub const DEFAULT_ARENA_SIZE: Vec2 = Vec2::new(900.0, 600.0);
+
+/// A level layout loaded from a `.level` file.
+#[derive(Asset, TypePath)]
+pub struct LevelAsset {
+    pub brick_layout: Vec<Vec<GridCell>>,
+    /// World-space (width, height) of the arena this level's bricks and
+    /// walls are laid out in; may be larger than the viewport.
+    pub arena_size: Vec2,
+}
+
+#[derive(Default)]
+pub struct LevelAssetLoader;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LevelAssetLoaderError {
+    #[error("could not read level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid brick cell {cell:?} on row {row}")]
+    InvalidCell { cell: String, row: usize },
+    #[error("level file has no brick rows")]
+    EmptyLevel,
+}
+
+impl AssetLoader for LevelAssetLoader {
+    type Asset = LevelAsset;
+    type Settings = ();
+    type Error = LevelAssetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut Reader<'_>,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<LevelAsset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        parse_level(BufReader::new(bytes.as_slice()))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level"]
+    }
+}
+
+fn parse_level(reader: impl BufRead) -> Result<LevelAsset, LevelAssetLoaderError> {
+    let mut brick_layout = Vec::new();
+    let mut arena_size = DEFAULT_ARENA_SIZE;
+    let mut header_checked = false;
+
+    for (row, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if !header_checked {
+            header_checked = true;
+            if let Some(size) = parse_arena_size(line) {
+                arena_size = size;
+                continue;
+            }
+        }
+
+        let row_cells = line
+            .split_whitespace()
+            .map(|token| parse_cell(token, row))
+            .collect::<Result<Vec<_>, _>>()?;
+        brick_layout.push(row_cells);
+    }
+
+    if brick_layout.is_empty() {
+        return Err(LevelAssetLoaderError::EmptyLevel);
+    }
+
+    Ok(LevelAsset { brick_layout, arena_size })
+}
+
+/// Recognizes a lone `WIDTHxHEIGHT` header line (no spaces, so it can't be
+/// mistaken for a row of grid tokens).
+fn parse_arena_size(line: &str) -> Option<Vec2> {
+    let (width, height) = line.split_once('x')?;
+    Some(Vec2::new(width.parse().ok()?, height.parse().ok()?))
+}
+
+fn parse_cell(token: &str, row: usize) -> Result<GridCell, LevelAssetLoaderError> {
+    let (marker, color_override) = match token.split_once(':') {
+        Some((marker, hex)) => (marker, Some(parse_hex_color(hex, token, row)?)),
+        None => (token, None),
+    };
+
+    match marker {
+        "." => Ok(GridCell::Empty),
+        "#" => Ok(GridCell::Brick(BrickCell {
+            hit_points: u32::MAX,
+            score_value: 0,
+            indestructible: true,
+            color: color_override.unwrap_or(Color::srgb(0.6, 0.6, 0.6)),
+        })),
+        "/" => Ok(GridCell::Slope(SlopeCell {
+            normal: Vec2::new(-1.0, 1.0).normalize(),
+        })),
+        "\\" => Ok(GridCell::Slope(SlopeCell {
+            normal: Vec2::new(1.0, 1.0).normalize(),
+        })),
+        digits => {
+            let hit_points: u32 = digits.parse().map_err(|_| LevelAssetLoaderError::InvalidCell {
+                cell: token.to_string(),
+                row,
+            })?;
+            Ok(GridCell::Brick(BrickCell {
+                hit_points,
+                // Tougher bricks are worth more; simple multiple of hit points
+                // keeps the format free of a separate score column.
+                score_value: hit_points as usize * 10,
+                indestructible: false,
+                color: color_override.unwrap_or(Color::srgb(0.5, 0.5, 1.0)),
+            }))
+        }
+    }
+}
+
+fn parse_hex_color(hex: &str, token: &str, row: usize) -> Result<Color, LevelAssetLoaderError> {
+    if hex.len() != 6 {
+        return Err(LevelAssetLoaderError::InvalidCell {
+            cell: token.to_string(),
+            row,
+        });
+    }
+    let channel = |range| {
+        u8::from_str_radix(&hex[range], 16)
+            .map_err(|_| LevelAssetLoaderError::InvalidCell {
+                cell: token.to_string(),
+                row,
+            })
+    };
+    let r = channel(0..2)?;
+    let g = channel(2..4)?;
+    let b = channel(4..6)?;
+    Ok(Color::srgb_u8(r, g, b))
+}